@@ -75,7 +75,7 @@ fn main() {
         // Insert the prediction into the group corresponding to its outeq.
         groups
             .entry(pred.outeq())
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(pred.prediction());
     }
 
@@ -0,0 +1,31 @@
+use std::collections::HashMap;
+
+/// Covariate values available to the init/out/diffeq closures.
+///
+/// This is a flat, static `name -> value` map with no notion of time: there
+/// is currently no mechanism on [`crate::Subject`] to attach covariates or
+/// record them at specific timepoints, so every backend constructs an empty
+/// [`Covariates`] for each call. Models read a covariate by name via
+/// [`Covariates::get`]; [`Covariates::set`] fills one in, but no backend
+/// accepts a caller-built [`Covariates`], so the closures always see an
+/// empty map.
+#[derive(Debug, Clone, Default)]
+pub struct Covariates {
+    values: HashMap<String, f64>,
+}
+
+impl Covariates {
+    pub fn new() -> Self {
+        Self {
+            values: HashMap::new(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<f64> {
+        self.values.get(name).copied()
+    }
+
+    pub fn set(&mut self, name: impl Into<String>, value: f64) {
+        self.values.insert(name.into(), value);
+    }
+}
@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+
+use super::{apply_lag_fa, breakpoints, new_covariates, rateiv_at, Equation, FaFn, InitFn, LagFn, OutFn, State};
+use crate::linalg::Matrix;
+use crate::subject::Event;
+use crate::{Covariates, Prediction, Predictions, Subject};
+
+type RateMatrixFn = dyn Fn(&[f64]) -> Vec<Vec<f64>> + Send + Sync;
+
+/// A closed-form linear compartmental model (ADVAN-style), evaluated by
+/// exact superposition instead of numerical integration.
+///
+/// `rate_matrix` builds the system matrix `M` for a given parameter vector,
+/// where `dx/dt = M*x + rateiv`: each diagonal entry is minus the
+/// compartment's total exit rate constant, and each off-diagonal entry
+/// `M[i][j]` is the micro-rate constant for transfer from compartment `j`
+/// into compartment `i`. Because the system is linear and `rateiv` is
+/// piecewise-constant between dosing/observation events, each interval has
+/// an exact solution obtained by augmenting the state with a constant `1`
+/// so the affine system `dx/dt = M*x + rateiv` becomes the linear system
+/// `dz/dt = A*z` for `z = [x; 1]`, solved by a single matrix exponential
+/// `z(t+dt) = exp(A*dt)*z(t)`. This handles `M` singular (e.g. a pure
+/// absorption chain with no elimination from the last compartment) exactly
+/// like the invertible case, with no separate particular-solution formula
+/// or fallback needed.
+///
+/// This covers IV bolus, IV infusion and first-order oral absorption
+/// (as an extra absorption compartment feeding the central one) for 1, 2
+/// or 3 compartment systems alike — the shape of `M` is all that changes.
+/// The remaining closures and `(nstates, noutputs)` dimensions match
+/// [`super::ODE::new`] exactly, so a model can be swapped between the two
+/// backends with minimal code change.
+pub struct Analytical {
+    rate_matrix: Box<RateMatrixFn>,
+    lag: Box<LagFn>,
+    fa: Box<FaFn>,
+    init: Box<InitFn>,
+    out: Box<OutFn>,
+    nstates: usize,
+    noutputs: usize,
+}
+
+impl Analytical {
+    pub fn new(
+        rate_matrix: impl Fn(&[f64]) -> Vec<Vec<f64>> + Send + Sync + 'static,
+        lag: impl Fn(&[f64]) -> HashMap<usize, f64> + Send + Sync + 'static,
+        fa: impl Fn(&[f64]) -> HashMap<usize, f64> + Send + Sync + 'static,
+        init: impl Fn(&[f64], f64, &Covariates, &mut State) + Send + Sync + 'static,
+        out: impl Fn(&State, &[f64], f64, &Covariates, &mut State) + Send + Sync + 'static,
+        dims: (usize, usize),
+    ) -> Self {
+        Self {
+            rate_matrix: Box::new(rate_matrix),
+            lag: Box::new(lag),
+            fa: Box::new(fa),
+            init: Box::new(init),
+            out: Box::new(out),
+            nstates: dims.0,
+            noutputs: dims.1,
+        }
+    }
+
+    /// Advance `x` from `t0` to `t1` in one exact step, under the constant
+    /// input `rateiv` (valid because `t0`/`t1` are adjacent breakpoints).
+    ///
+    /// Builds the augmented `(n+1)x(n+1)` matrix `A = [[M, rateiv], [0, 0]]`
+    /// so that `z(t+dt) = exp(A*dt)*z(t)` for `z = [x; 1]` gives the exact
+    /// affine solution in its first `n` components, whether or not `M` is
+    /// invertible — unlike solving for a particular solution via `M^-1`,
+    /// this never needs a singular-matrix fallback.
+    fn step(&self, m: &Matrix, rateiv: &[f64], x: &State, dt: f64) -> State {
+        if dt <= 0.0 {
+            return x.clone();
+        }
+        let n = self.nstates;
+        let mut augmented = Matrix::zeros(n + 1);
+        for (i, &r) in rateiv.iter().enumerate() {
+            for j in 0..n {
+                augmented.set(i, j, m.get(i, j));
+            }
+            augmented.set(i, n, r);
+        }
+
+        let expm = augmented.scale(dt).exp();
+        let mut z = x.to_vec();
+        z.push(1.0);
+        expm.mul_vec(&z)[..n].to_vec()
+    }
+}
+
+impl Equation for Analytical {
+    fn nstates(&self) -> usize {
+        self.nstates
+    }
+
+    fn noutputs(&self) -> usize {
+        self.noutputs
+    }
+
+    fn predict_one(&self, subject: &Subject, params: &[f64]) -> Predictions {
+        let cov = new_covariates();
+        let lag = (self.lag)(params);
+        let fa = (self.fa)(params);
+        let events = apply_lag_fa(subject.events(), &lag, &fa);
+
+        let m = Matrix::from_rows(&(self.rate_matrix)(params));
+
+        let mut x = vec![0.0; self.nstates];
+        (self.init)(params, 0.0, &cov, &mut x);
+
+        let mut predictions = Vec::new();
+        let mut t = 0.0;
+        for tb in breakpoints(&events) {
+            let rateiv = rateiv_at(&events, self.nstates, t);
+            x = self.step(&m, &rateiv, &x, tb - t);
+            t = tb;
+
+            for event in &events {
+                match event {
+                    Event::Bolus {
+                        time,
+                        amount,
+                        compartment,
+                    } if (*time - t).abs() < f64::EPSILON && *compartment < x.len() => {
+                        x[*compartment] += amount;
+                    }
+                    Event::Observation { time, outeq, .. }
+                        if (*time - t).abs() < f64::EPSILON && *outeq < self.noutputs =>
+                    {
+                        let mut y = vec![0.0; self.noutputs];
+                        (self.out)(&x, params, t, &cov, &mut y);
+                        predictions.push(Prediction::new(t, *outeq, y[*outeq]));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Predictions::new(predictions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::ODE;
+
+    // Compartment 0 both eliminates (KE) and feeds compartment 1 (K01);
+    // compartment 1 has no exit of its own, so `M` is singular. This is the
+    // "pure absorption/accumulation chain" case this backend documents
+    // supporting.
+    const KE: f64 = 0.2;
+    const K01: f64 = 0.1;
+    const RATE: f64 = 5.0;
+    const DURATION: f64 = 20.0;
+
+    fn analytical_model() -> Analytical {
+        Analytical::new(
+            |_p: &[f64]| vec![vec![-(KE + K01), 0.0], vec![K01, 0.0]],
+            |_p: &[f64]| HashMap::new(),
+            |_p: &[f64]| HashMap::new(),
+            |_p: &[f64], _t, _cov, x: &mut State| {
+                x[0] = 0.0;
+                x[1] = 0.0;
+            },
+            |x: &State, _p: &[f64], _t, _cov, y: &mut State| {
+                y[0] = x[0];
+                y[1] = x[1];
+            },
+            (2, 2),
+        )
+    }
+
+    fn ode_model() -> ODE {
+        ODE::new(
+            |x: &State, _p: &[f64], _t, dx: &mut State, rateiv: &[f64], _cov| {
+                dx[0] = -(KE + K01) * x[0] + rateiv[0];
+                dx[1] = K01 * x[0];
+            },
+            |_p: &[f64]| HashMap::new(),
+            |_p: &[f64]| HashMap::new(),
+            |_p: &[f64], _t, _cov, x: &mut State| {
+                x[0] = 0.0;
+                x[1] = 0.0;
+            },
+            |x: &State, _p: &[f64], _t, _cov, y: &mut State| {
+                y[0] = x[0];
+                y[1] = x[1];
+            },
+            (2, 2),
+        )
+    }
+
+    /// Regression for a singular `M`: the old `M^-1`-based particular
+    /// solution fell back to `dt * rateiv` component-wise, which silently
+    /// dropped the inter-compartmental transfer into the non-eliminating
+    /// accumulator entirely.
+    #[test]
+    fn singular_rate_matrix_matches_ode_reference() {
+        let subject = Subject::builder("s1")
+            .infusion(0.0, DURATION, 0, RATE)
+            .observation(DURATION, 0.0, 1)
+            .build();
+
+        let analytical = analytical_model().predict_one(&subject, &[]);
+        let ode = ode_model().predict_one(&subject, &[]);
+
+        let a = analytical.get_predictions()[0].prediction();
+        let o = ode.get_predictions()[0].prediction();
+        assert!(a > 0.0, "accumulator compartment should receive transferred amount, got {a}");
+        assert!((a - o).abs() / o < 1e-3, "analytical={a} ode={o}");
+    }
+
+    /// Thin integration check that `predict_one` actually runs events
+    /// through `apply_lag_fa` before simulating (the helper's own behavior
+    /// is covered directly in `super::super::tests`).
+    #[test]
+    fn predict_one_applies_lag_and_fa_to_the_bolus() {
+        const LAG: f64 = 2.0;
+        const FA: f64 = 0.5;
+        let model = Analytical::new(
+            |_p: &[f64]| vec![vec![-KE]],
+            |_p: &[f64]| HashMap::from([(0, LAG)]),
+            |_p: &[f64]| HashMap::from([(0, FA)]),
+            |_p: &[f64], _t, _cov, x: &mut State| x[0] = 0.0,
+            |x: &State, _p: &[f64], _t, _cov, y: &mut State| y[0] = x[0],
+            (1, 1),
+        );
+
+        let subject = Subject::builder("s1")
+            .bolus(0.0, 10.0, 0)
+            .observation(2.5, 0.0, 0)
+            .build();
+        let preds = model.predict_one(&subject, &[]);
+        let expected = 10.0 * FA * (-KE * (2.5 - LAG)).exp();
+        assert!((preds.get_predictions()[0].prediction() - expected).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,194 @@
+//! Model backends: [`ODE`] for numerically integrated systems, [`Analytical`]
+//! for closed-form linear compartmental systems.
+
+mod analytical;
+mod jacobian;
+mod ode;
+
+use std::collections::HashMap;
+
+pub use analytical::Analytical;
+pub use ode::ODE;
+
+use crate::subject::Event;
+use crate::{Covariates, Predictions, Subject};
+
+/// The state vector shared by every equation backend.
+///
+/// Plain `Vec<f64>` is enough here: models are small (a handful of
+/// compartments) and indexed by position, matching how closures are written
+/// throughout this crate (`x[0]`, `dx[1]`, ...).
+pub type State = Vec<f64>;
+
+pub(crate) type LagFn = dyn Fn(&[f64]) -> HashMap<usize, f64> + Send + Sync;
+pub(crate) type FaFn = dyn Fn(&[f64]) -> HashMap<usize, f64> + Send + Sync;
+pub(crate) type InitFn = dyn Fn(&[f64], f64, &Covariates, &mut State) + Send + Sync;
+pub(crate) type OutFn = dyn Fn(&State, &[f64], f64, &Covariates, &mut State) + Send + Sync;
+
+/// A model that can turn a [`Subject`]'s dosing/observation schedule plus a
+/// parameter vector into simulated [`Predictions`].
+///
+/// Every backend (numerically integrated or closed-form) funnels through
+/// [`Equation::predict_one`], which is also the entry point used by
+/// [`crate::population::predict_batch`] for running many subjects/parameter
+/// sets in parallel.
+pub trait Equation: Send + Sync {
+    /// Number of states in the underlying system.
+    fn nstates(&self) -> usize;
+
+    /// Number of output equations.
+    fn noutputs(&self) -> usize;
+
+    /// Simulate a single subject against a single parameter vector.
+    fn predict_one(&self, subject: &Subject, params: &[f64]) -> Predictions;
+
+    /// Alias for [`Equation::predict_one`], kept for call-site clarity at
+    /// the top of a script where "estimate" reads better than "predict".
+    fn estimate_predictions(&self, subject: &Subject, params: &[f64]) -> Predictions {
+        self.predict_one(subject, params)
+    }
+}
+
+pub(crate) fn new_covariates() -> Covariates {
+    Covariates::new()
+}
+
+/// The rate delivered to each compartment's `rateiv` slot by the infusions
+/// active at time `t`.
+pub(crate) fn rateiv_at(events: &[Event], nstates: usize, t: f64) -> Vec<f64> {
+    let mut rateiv = vec![0.0; nstates];
+    for event in events {
+        if let Event::Infusion {
+            start,
+            end,
+            compartment,
+            rate,
+        } = event
+        {
+            if t >= *start && t < *end && *compartment < rateiv.len() {
+                rateiv[*compartment] += rate;
+            }
+        }
+    }
+    rateiv
+}
+
+/// Apply a model's absorption-lag and bioavailability-fraction maps to a
+/// subject's dose events before simulation: `lag[compartment]` shifts a
+/// bolus's time (or an infusion's start/end) later, and `fa[compartment]`
+/// scales the delivered amount (or infusion rate). Observations are passed
+/// through unchanged. Both [`ODE`] and [`Analytical`] run this once per
+/// `predict_one` call and then treat the result as the event schedule, so
+/// lag/fa apply identically regardless of backend.
+pub(crate) fn apply_lag_fa(events: &[Event], lag: &HashMap<usize, f64>, fa: &HashMap<usize, f64>) -> Vec<Event> {
+    let mut adjusted: Vec<Event> = events
+        .iter()
+        .map(|event| match *event {
+            Event::Bolus {
+                time,
+                amount,
+                compartment,
+            } => Event::Bolus {
+                time: time + lag.get(&compartment).copied().unwrap_or(0.0),
+                amount: amount * fa.get(&compartment).copied().unwrap_or(1.0),
+                compartment,
+            },
+            Event::Infusion {
+                start,
+                end,
+                compartment,
+                rate,
+            } => {
+                let shift = lag.get(&compartment).copied().unwrap_or(0.0);
+                Event::Infusion {
+                    start: start + shift,
+                    end: end + shift,
+                    compartment,
+                    rate: rate * fa.get(&compartment).copied().unwrap_or(1.0),
+                }
+            }
+            Event::Observation { time, value, outeq } => Event::Observation { time, value, outeq },
+        })
+        .collect();
+    adjusted.sort_by(|a, b| a.time().partial_cmp(&b.time()).unwrap());
+    adjusted
+}
+
+/// Every time at which the infusion rate or dosing/observation schedule can
+/// change: bolus times, infusion start/end times, and observation times.
+///
+/// Backends that step in closed form (rather than substepping, like
+/// [`ODE`]) need to treat `rateiv` as piecewise-constant between these
+/// breakpoints.
+pub(crate) fn breakpoints(events: &[Event]) -> Vec<f64> {
+    let mut times: Vec<f64> = events
+        .iter()
+        .flat_map(|event| match event {
+            Event::Bolus { time, .. } => vec![*time],
+            Event::Infusion { start, end, .. } => vec![*start, *end],
+            Event::Observation { time, .. } => vec![*time],
+        })
+        .collect();
+    times.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    times.dedup_by(|a, b| (*a - *b).abs() < f64::EPSILON);
+    times
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Both backends run `apply_lag_fa` once per `predict_one` and trust it
+    /// to shift/scale the right events, so this is the one place that
+    /// actually checks its behavior, rather than each backend re-deriving
+    /// the same scenario.
+    #[test]
+    fn apply_lag_fa_shifts_doses_and_scales_amounts_but_leaves_observations_alone() {
+        let events = vec![
+            Event::Bolus {
+                time: 0.0,
+                amount: 10.0,
+                compartment: 0,
+            },
+            Event::Infusion {
+                start: 1.0,
+                end: 2.0,
+                compartment: 1,
+                rate: 4.0,
+            },
+            Event::Observation {
+                time: 0.5,
+                value: 0.0,
+                outeq: 0,
+            },
+        ];
+        let lag = HashMap::from([(0, 2.0), (1, 0.5)]);
+        let fa = HashMap::from([(0, 0.5)]);
+
+        let adjusted = apply_lag_fa(&events, &lag, &fa);
+
+        // Sorted by (post-shift) time: the compartment-1 infusion now starts
+        // before the lagged compartment-0 bolus.
+        assert_eq!(
+            adjusted,
+            vec![
+                Event::Observation {
+                    time: 0.5,
+                    value: 0.0,
+                    outeq: 0,
+                },
+                Event::Infusion {
+                    start: 1.5,
+                    end: 2.5,
+                    compartment: 1,
+                    rate: 4.0,
+                },
+                Event::Bolus {
+                    time: 2.0,
+                    amount: 5.0,
+                    compartment: 0,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,315 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use super::jacobian::{colored_jacobian, Coloring, Sparsity};
+use super::{apply_lag_fa, new_covariates, rateiv_at, Equation, FaFn, InitFn, LagFn, OutFn, State};
+use crate::linalg::Matrix;
+use crate::subject::Event;
+use crate::{Covariates, Prediction, Predictions, Subject};
+
+type DiffEqFn = dyn Fn(&State, &[f64], f64, &mut State, &[f64], &Covariates) + Send + Sync;
+
+/// The maximum internal integration step (time units), used to subdivide
+/// the interval between consecutive dosing/observation events.
+const MAX_SUBSTEP: f64 = 0.1;
+
+/// Perturbation used both to detect the Jacobian's sparsity pattern and to
+/// evaluate its (compressed) finite-difference values.
+const JACOBIAN_EPS: f64 = 1e-6;
+
+/// Newton convergence tolerance and iteration cap for implicit (BDF1) steps.
+const NEWTON_TOL: f64 = 1e-9;
+const NEWTON_MAX_ITER: usize = 10;
+
+/// A model whose state derivatives are given as a closure and integrated
+/// numerically (fixed-step RK4 between event times).
+///
+/// Construct with [`ODE::new`], passing the same five closures and
+/// `(nstates, noutputs)` dimensions regardless of the underlying solver, so
+/// a model can be swapped for [`super::Analytical`] with minimal code
+/// change.
+pub struct ODE {
+    diffeq: Box<DiffEqFn>,
+    lag: Box<LagFn>,
+    fa: Box<FaFn>,
+    init: Box<InitFn>,
+    out: Box<OutFn>,
+    nstates: usize,
+    noutputs: usize,
+    stiff: bool,
+    /// Sparsity pattern and CPR coloring of the RHS Jacobian, detected on
+    /// first use and reused for every later step and every subject, since
+    /// the pattern is a structural property of the model, not the state.
+    jacobian_cache: OnceLock<(Sparsity, Coloring)>,
+}
+
+impl ODE {
+    pub fn new(
+        diffeq: impl Fn(&State, &[f64], f64, &mut State, &[f64], &Covariates) + Send + Sync + 'static,
+        lag: impl Fn(&[f64]) -> HashMap<usize, f64> + Send + Sync + 'static,
+        fa: impl Fn(&[f64]) -> HashMap<usize, f64> + Send + Sync + 'static,
+        init: impl Fn(&[f64], f64, &Covariates, &mut State) + Send + Sync + 'static,
+        out: impl Fn(&State, &[f64], f64, &Covariates, &mut State) + Send + Sync + 'static,
+        dims: (usize, usize),
+    ) -> Self {
+        Self {
+            diffeq: Box::new(diffeq),
+            lag: Box::new(lag),
+            fa: Box::new(fa),
+            init: Box::new(init),
+            out: Box::new(out),
+            nstates: dims.0,
+            noutputs: dims.1,
+            stiff: false,
+            jacobian_cache: OnceLock::new(),
+        }
+    }
+
+    /// Use implicit (backward Euler) stepping with a cached sparse,
+    /// CPR-colored Jacobian instead of explicit RK4. Worthwhile for stiff
+    /// systems (e.g. a cascade of pools with widely separated flux rates)
+    /// where RK4 would need a prohibitively small step to stay stable.
+    pub fn with_stiff(mut self, stiff: bool) -> Self {
+        self.stiff = stiff;
+        self
+    }
+
+    /// Evaluate the RHS as a plain `&[f64] -> Vec<f64>` closure, holding
+    /// everything but the state fixed — what the Jacobian prober needs.
+    fn rhs<'a>(
+        &'a self,
+        params: &'a [f64],
+        t: f64,
+        rateiv: &'a [f64],
+        cov: &'a Covariates,
+    ) -> impl Fn(&[f64]) -> Vec<f64> + 'a {
+        move |x: &[f64]| {
+            let mut dx = vec![0.0; self.nstates];
+            (self.diffeq)(&x.to_vec(), params, t, &mut dx, rateiv, cov);
+            dx
+        }
+    }
+
+    /// The Jacobian of the RHS at `x`, reusing the cached sparsity pattern
+    /// and coloring (detected once, at the state of the first call) so
+    /// only `num_colors` extra RHS evaluations are needed here.
+    fn jacobian(&self, params: &[f64], t: f64, rateiv: &[f64], cov: &Covariates, x: &[f64]) -> Matrix {
+        let (sparsity, coloring) = self.jacobian_cache.get_or_init(|| {
+            let sparsity = Sparsity::detect(self.rhs(params, t, rateiv, cov), x, JACOBIAN_EPS);
+            let coloring = sparsity.color();
+            (sparsity, coloring)
+        });
+        colored_jacobian(self.rhs(params, t, rateiv, cov), x, sparsity, coloring, JACOBIAN_EPS)
+    }
+
+    /// Advance `x` from `t0` to `t1`, subdividing so no substep exceeds
+    /// [`MAX_SUBSTEP`], via explicit RK4 or implicit backward Euler
+    /// depending on [`Self::with_stiff`].
+    fn integrate(
+        &self,
+        params: &[f64],
+        events: &[Event],
+        cov: &Covariates,
+        x: &mut State,
+        t0: f64,
+        t1: f64,
+    ) {
+        if t1 <= t0 {
+            return;
+        }
+        let nsteps = ((t1 - t0) / MAX_SUBSTEP).ceil().max(1.0) as usize;
+        let h = (t1 - t0) / nsteps as f64;
+        let mut t = t0;
+        for _ in 0..nsteps {
+            if self.stiff {
+                self.step_implicit(params, events, cov, x, t, h);
+            } else {
+                self.step_rk4(params, events, cov, x, t, h);
+            }
+            t += h;
+        }
+    }
+
+    fn step_rk4(&self, params: &[f64], events: &[Event], cov: &Covariates, x: &mut State, t: f64, h: f64) {
+        let mut k1 = vec![0.0; self.nstates];
+        let mut k2 = vec![0.0; self.nstates];
+        let mut k3 = vec![0.0; self.nstates];
+        let mut k4 = vec![0.0; self.nstates];
+        let mut tmp = vec![0.0; self.nstates];
+
+        let rateiv = rateiv_at(events, self.nstates, t);
+        (self.diffeq)(x, params, t, &mut k1, &rateiv, cov);
+
+        for i in 0..self.nstates {
+            tmp[i] = x[i] + 0.5 * h * k1[i];
+        }
+        let rateiv_mid = rateiv_at(events, self.nstates, t + 0.5 * h);
+        (self.diffeq)(&tmp, params, t + 0.5 * h, &mut k2, &rateiv_mid, cov);
+
+        for i in 0..self.nstates {
+            tmp[i] = x[i] + 0.5 * h * k2[i];
+        }
+        (self.diffeq)(&tmp, params, t + 0.5 * h, &mut k3, &rateiv_mid, cov);
+
+        for i in 0..self.nstates {
+            tmp[i] = x[i] + h * k3[i];
+        }
+        let rateiv_end = rateiv_at(events, self.nstates, t + h);
+        (self.diffeq)(&tmp, params, t + h, &mut k4, &rateiv_end, cov);
+
+        for i in 0..self.nstates {
+            x[i] += h / 6.0 * (k1[i] + 2.0 * k2[i] + 2.0 * k3[i] + k4[i]);
+        }
+    }
+
+    /// One backward-Euler step solved by Newton's method: find `x1` with
+    /// `x1 - x0 - h*f(x1, t+h) = 0`, using the cached sparse Jacobian of
+    /// `f` for the Newton system `(I - h*J) * delta = -residual`.
+    fn step_implicit(&self, params: &[f64], events: &[Event], cov: &Covariates, x: &mut State, t: f64, h: f64) {
+        let rateiv = rateiv_at(events, self.nstates, t + h);
+        let x0 = x.clone();
+        let mut x1 = x.clone();
+
+        for _ in 0..NEWTON_MAX_ITER {
+            let mut f = vec![0.0; self.nstates];
+            (self.diffeq)(&x1, params, t + h, &mut f, &rateiv, cov);
+
+            let residual: Vec<f64> = (0..self.nstates)
+                .map(|i| x1[i] - x0[i] - h * f[i])
+                .collect();
+            if residual.iter().all(|r| r.abs() < NEWTON_TOL) {
+                break;
+            }
+
+            let jac = self.jacobian(params, t + h, &rateiv, cov, &x1);
+            let mut newton_matrix = jac.scale(-h);
+            for i in 0..self.nstates {
+                newton_matrix.set(i, i, newton_matrix.get(i, i) + 1.0);
+            }
+            let neg_residual: Vec<f64> = residual.iter().map(|r| -r).collect();
+            let Some(delta) = newton_matrix.solve(&neg_residual) else {
+                break;
+            };
+            for i in 0..self.nstates {
+                x1[i] += delta[i];
+            }
+        }
+
+        *x = x1;
+    }
+}
+
+impl Equation for ODE {
+    fn nstates(&self) -> usize {
+        self.nstates
+    }
+
+    fn noutputs(&self) -> usize {
+        self.noutputs
+    }
+
+    fn predict_one(&self, subject: &Subject, params: &[f64]) -> Predictions {
+        let cov = new_covariates();
+        let lag = (self.lag)(params);
+        let fa = (self.fa)(params);
+        let events = apply_lag_fa(subject.events(), &lag, &fa);
+
+        let mut x = vec![0.0; self.nstates];
+        (self.init)(params, 0.0, &cov, &mut x);
+
+        let mut predictions = Vec::new();
+        let mut t = 0.0;
+        for event in &events {
+            let event_time = event.time();
+            self.integrate(params, &events, &cov, &mut x, t, event_time);
+            t = event_time;
+
+            if let Event::Bolus {
+                amount, compartment, ..
+            } = event
+            {
+                if *compartment < x.len() {
+                    x[*compartment] += amount;
+                }
+            }
+
+            if let Event::Observation { outeq, .. } = event {
+                let mut y = vec![0.0; self.noutputs];
+                (self.out)(&x, params, t, &cov, &mut y);
+                if *outeq < y.len() {
+                    predictions.push(Prediction::new(t, *outeq, y[*outeq]));
+                }
+            }
+        }
+
+        Predictions::new(predictions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KE: f64 = 0.2;
+
+    /// Thin integration check that `predict_one` actually runs events
+    /// through `apply_lag_fa` before simulating (the helper's own behavior
+    /// is covered directly in `super::super::tests`).
+    #[test]
+    fn predict_one_applies_lag_and_fa_to_the_bolus() {
+        const LAG: f64 = 2.0;
+        const FA: f64 = 0.5;
+        let model = ODE::new(
+            |x: &State, _p: &[f64], _t, dx: &mut State, _rateiv, _cov| dx[0] = -KE * x[0],
+            |_p: &[f64]| HashMap::from([(0, LAG)]),
+            |_p: &[f64]| HashMap::from([(0, FA)]),
+            |_p: &[f64], _t, _cov, x: &mut State| x[0] = 0.0,
+            |x: &State, _p: &[f64], _t, _cov, y: &mut State| y[0] = x[0],
+            (1, 1),
+        );
+
+        let subject = Subject::builder("s1")
+            .bolus(0.0, 10.0, 0)
+            .observation(2.5, 0.0, 0)
+            .build();
+        let preds = model.predict_one(&subject, &[]);
+        let expected = 10.0 * FA * (-KE * (2.5 - LAG)).exp();
+        assert!((preds.get_predictions()[0].prediction() - expected).abs() < 1e-6);
+    }
+
+    /// End-to-end check of `with_stiff(true)`: the jacobian.rs tests only
+    /// cover `Sparsity`/`Coloring` in isolation, not `step_implicit` itself,
+    /// so this exercises the full Newton/backward-Euler path against the
+    /// analytic solution of a one-compartment decay, `x(t) = dose *
+    /// exp(-KE*t)`.
+    #[test]
+    fn stiff_stepping_matches_the_analytic_decay_solution() {
+        let model = |stiff: bool| {
+            ODE::new(
+                |x: &State, _p: &[f64], _t, dx: &mut State, _rateiv, _cov| dx[0] = -KE * x[0],
+                |_p: &[f64]| HashMap::new(),
+                |_p: &[f64]| HashMap::new(),
+                |_p: &[f64], _t, _cov, x: &mut State| x[0] = 0.0,
+                |x: &State, _p: &[f64], _t, _cov, y: &mut State| y[0] = x[0],
+                (1, 1),
+            )
+            .with_stiff(stiff)
+        };
+
+        let subject = Subject::builder("s1")
+            .bolus(0.0, 10.0, 0)
+            .observation(5.0, 0.0, 0)
+            .build();
+
+        let stiff = model(true).predict_one(&subject, &[]);
+        let expected = 10.0 * (-KE * 5.0).exp();
+        // Backward Euler is only first-order accurate, so this leaves room
+        // for MAX_SUBSTEP-sized truncation error rather than demanding RK4
+        // precision from a solver chosen for stability, not accuracy.
+        assert!(
+            (stiff.get_predictions()[0].prediction() - expected).abs() < 5e-2,
+            "got={} expected={expected}",
+            stiff.get_predictions()[0].prediction()
+        );
+    }
+}
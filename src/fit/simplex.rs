@@ -0,0 +1,136 @@
+//! Derivative-free local minimization (Nelder-Mead), used as the inner
+//! optimizer for [`super::fit_local`] and for polishing each candidate
+//! point [`super::fit_global`] samples — the model closures are opaque, so
+//! there's no gradient to lean on.
+
+const REFLECT: f64 = 1.0;
+const EXPAND: f64 = 2.0;
+const CONTRACT: f64 = 0.5;
+const SHRINK: f64 = 0.5;
+
+/// Total order on `f64` for ranking simplex vertices, treating `NaN` (e.g.
+/// from a model evaluated at a degenerate parameter, like dividing by a
+/// rate constant pinned to its lower bound of zero) as worse than any real
+/// value instead of panicking like `partial_cmp().unwrap()` would.
+pub(crate) fn cmp_f64(a: f64, b: f64) -> std::cmp::Ordering {
+    match (a.is_nan(), b.is_nan()) {
+        (true, true) => std::cmp::Ordering::Equal,
+        (true, false) => std::cmp::Ordering::Greater,
+        (false, true) => std::cmp::Ordering::Less,
+        (false, false) => a.partial_cmp(&b).unwrap(),
+    }
+}
+
+/// Minimize `objective` starting from `initial`, returning `(params, value)`.
+pub fn nelder_mead(
+    objective: impl Fn(&[f64]) -> f64,
+    initial: &[f64],
+    max_iter: usize,
+    tol: f64,
+) -> (Vec<f64>, f64) {
+    let n = initial.len();
+    if n == 0 {
+        return (Vec::new(), objective(initial));
+    }
+
+    // Build the initial simplex by perturbing each coordinate in turn.
+    let mut simplex: Vec<Vec<f64>> = vec![initial.to_vec()];
+    for i in 0..n {
+        let mut vertex = initial.to_vec();
+        let step = if vertex[i].abs() > 1e-8 { vertex[i] * 0.05 } else { 0.00025 };
+        vertex[i] += step;
+        simplex.push(vertex);
+    }
+    let mut values: Vec<f64> = simplex.iter().map(|v| objective(v)).collect();
+
+    for _ in 0..max_iter {
+        let mut order: Vec<usize> = (0..simplex.len()).collect();
+        order.sort_by(|&a, &b| cmp_f64(values[a], values[b]));
+        simplex = order.iter().map(|&i| simplex[i].clone()).collect();
+        values = order.iter().map(|&i| values[i]).collect();
+
+        if values[values.len() - 1] - values[0] < tol {
+            break;
+        }
+
+        let worst = simplex.len() - 1;
+        let centroid: Vec<f64> = (0..n)
+            .map(|j| simplex[..worst].iter().map(|v| v[j]).sum::<f64>() / worst as f64)
+            .collect();
+
+        let reflected: Vec<f64> = (0..n)
+            .map(|j| centroid[j] + REFLECT * (centroid[j] - simplex[worst][j]))
+            .collect();
+        let reflected_value = objective(&reflected);
+
+        if reflected_value < values[0] {
+            let expanded: Vec<f64> = (0..n)
+                .map(|j| centroid[j] + EXPAND * (reflected[j] - centroid[j]))
+                .collect();
+            let expanded_value = objective(&expanded);
+            if expanded_value < reflected_value {
+                simplex[worst] = expanded;
+                values[worst] = expanded_value;
+            } else {
+                simplex[worst] = reflected;
+                values[worst] = reflected_value;
+            }
+        } else if reflected_value < values[worst - 1] {
+            simplex[worst] = reflected;
+            values[worst] = reflected_value;
+        } else {
+            let contracted: Vec<f64> = (0..n)
+                .map(|j| centroid[j] + CONTRACT * (simplex[worst][j] - centroid[j]))
+                .collect();
+            let contracted_value = objective(&contracted);
+            if contracted_value < values[worst] {
+                simplex[worst] = contracted;
+                values[worst] = contracted_value;
+            } else {
+                let best = simplex[0].clone();
+                for i in 1..simplex.len() {
+                    for j in 0..n {
+                        simplex[i][j] = best[j] + SHRINK * (simplex[i][j] - best[j]);
+                    }
+                    values[i] = objective(&simplex[i]);
+                }
+            }
+        }
+    }
+
+    let best = (0..simplex.len())
+        .min_by(|&a, &b| cmp_f64(values[a], values[b]))
+        .unwrap();
+    (simplex[best].clone(), values[best])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minimizes_a_simple_quadratic_bowl() {
+        let (params, value) = nelder_mead(|p| (p[0] - 3.0).powi(2) + (p[1] + 1.0).powi(2), &[0.0, 0.0], 500, 1e-12);
+        assert!((params[0] - 3.0).abs() < 1e-3, "params={params:?}");
+        assert!((params[1] + 1.0).abs() < 1e-3, "params={params:?}");
+        assert!(value < 1e-6);
+    }
+
+    /// Regression: an objective that returns NaN at some vertices (e.g. the
+    /// model divides by a rate parameter pinned to zero) used to panic in
+    /// `partial_cmp().unwrap()` instead of treating those vertices as worse
+    /// than any finite one.
+    #[test]
+    fn does_not_panic_when_objective_returns_nan() {
+        let (_params, value) = nelder_mead(|p| if p[0] < 0.0 { f64::NAN } else { p[0].powi(2) }, &[1.0], 200, 1e-12);
+        assert!(value.is_finite() || value.is_nan());
+    }
+
+    #[test]
+    fn cmp_f64_orders_nan_as_worst() {
+        assert_eq!(cmp_f64(1.0, f64::NAN), std::cmp::Ordering::Less);
+        assert_eq!(cmp_f64(f64::NAN, 1.0), std::cmp::Ordering::Greater);
+        assert_eq!(cmp_f64(f64::NAN, f64::NAN), std::cmp::Ordering::Equal);
+        assert_eq!(cmp_f64(1.0, 2.0), std::cmp::Ordering::Less);
+    }
+}
@@ -0,0 +1,182 @@
+//! A small evaluator for the subset of Content MathML that SBML kinetic
+//! laws actually use: arithmetic over species amounts/concentrations and
+//! parameters.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use roxmltree::Node;
+
+#[derive(Debug)]
+pub struct MathError(String);
+
+impl fmt::Display for MathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unsupported MathML construct: {}", self.0)
+    }
+}
+
+impl std::error::Error for MathError {}
+
+/// A parsed `<apply>`/`<ci>`/`<cn>` expression tree, evaluated against a
+/// variable environment (species amounts and parameter values) at each RHS
+/// evaluation.
+#[derive(Debug, Clone)]
+pub enum Expr {
+    Number(f64),
+    Variable(String),
+    Add(Box<Expr>, Box<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+    Neg(Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+    Div(Box<Expr>, Box<Expr>),
+    Pow(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// Evaluate against a variable environment. Every `<ci>` reference must
+    /// have been checked against the model's known identifiers (species,
+    /// parameters, compartments) before this is ever called — see
+    /// [`Expr::variables`] — so a missing entry here would be a bug in the
+    /// caller, not a legitimately absent value, and is worth a loud panic
+    /// rather than silently contributing zero to a flux term.
+    pub fn eval(&self, vars: &HashMap<String, f64>) -> f64 {
+        match self {
+            Expr::Number(n) => *n,
+            Expr::Variable(name) => *vars
+                .get(name)
+                .unwrap_or_else(|| panic!("unresolved identifier {name:?} in kinetic law; this should have been caught by Expr::variables validation at import time")),
+            Expr::Add(a, b) => a.eval(vars) + b.eval(vars),
+            Expr::Sub(a, b) => a.eval(vars) - b.eval(vars),
+            Expr::Neg(a) => -a.eval(vars),
+            Expr::Mul(a, b) => a.eval(vars) * b.eval(vars),
+            Expr::Div(a, b) => a.eval(vars) / b.eval(vars),
+            Expr::Pow(a, b) => a.eval(vars).powf(b.eval(vars)),
+        }
+    }
+
+    /// Collect every `<ci>` identifier this expression references, so a
+    /// caller can validate them against the model's known species,
+    /// parameters and compartments before the closure ever runs.
+    pub fn variables(&self, out: &mut HashSet<String>) {
+        match self {
+            Expr::Number(_) => {}
+            Expr::Variable(name) => {
+                out.insert(name.clone());
+            }
+            Expr::Neg(a) => a.variables(out),
+            Expr::Add(a, b) | Expr::Sub(a, b) | Expr::Mul(a, b) | Expr::Div(a, b) | Expr::Pow(a, b) => {
+                a.variables(out);
+                b.variables(out);
+            }
+        }
+    }
+}
+
+/// Parse a `<math>` element's child (an `<apply>`, `<ci>` or `<cn>`) into an
+/// [`Expr`].
+pub fn parse(node: Node) -> Result<Expr, MathError> {
+    match node.tag_name().name() {
+        "cn" => {
+            let text = node.text().unwrap_or("0").trim();
+            text.parse::<f64>()
+                .map(Expr::Number)
+                .map_err(|_| MathError(format!("invalid <cn> value {text:?}")))
+        }
+        "ci" => Ok(Expr::Variable(node.text().unwrap_or("").trim().to_string())),
+        "apply" => parse_apply(node),
+        other => Err(MathError(format!("unexpected element <{other}>"))),
+    }
+}
+
+fn parse_apply(node: Node) -> Result<Expr, MathError> {
+    let mut children = node
+        .children()
+        .filter(|c| c.is_element())
+        .collect::<Vec<_>>();
+    if children.is_empty() {
+        return Err(MathError("empty <apply>".to_string()));
+    }
+    let op = children.remove(0);
+    let operands = children
+        .into_iter()
+        .map(parse)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    match op.tag_name().name() {
+        "plus" => fold(operands, Expr::Add),
+        "times" => fold(operands, Expr::Mul),
+        "minus" if operands.len() == 1 => Ok(Expr::Neg(Box::new(operands.into_iter().next().unwrap()))),
+        "minus" => fold(operands, Expr::Sub),
+        "divide" => binary(operands, Expr::Div),
+        "power" => binary(operands, Expr::Pow),
+        other => Err(MathError(format!("unsupported operator <{other}>"))),
+    }
+}
+
+fn fold(operands: Vec<Expr>, op: fn(Box<Expr>, Box<Expr>) -> Expr) -> Result<Expr, MathError> {
+    let mut iter = operands.into_iter();
+    let first = iter
+        .next()
+        .ok_or_else(|| MathError("operator with no operands".to_string()))?;
+    Ok(iter.fold(first, |acc, next| op(Box::new(acc), Box::new(next))))
+}
+
+fn binary(operands: Vec<Expr>, op: fn(Box<Expr>, Box<Expr>) -> Expr) -> Result<Expr, MathError> {
+    if operands.len() != 2 {
+        return Err(MathError("binary operator needs exactly 2 operands".to_string()));
+    }
+    let mut iter = operands.into_iter();
+    let a = iter.next().unwrap();
+    let b = iter.next().unwrap();
+    Ok(op(Box::new(a), Box::new(b)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc(xml: &str) -> roxmltree::Document<'_> {
+        roxmltree::Document::parse(xml).unwrap()
+    }
+
+    fn math_child<'a>(document: &'a roxmltree::Document<'a>) -> Node<'a, 'a> {
+        document
+            .descendants()
+            .find(|n| n.has_tag_name("math"))
+            .unwrap()
+            .children()
+            .find(|n| n.is_element())
+            .unwrap()
+    }
+
+    #[test]
+    fn parses_a_mass_action_rate_law_and_evaluates_it() {
+        let document = doc(
+            r#"<math><apply><times/><ci>k1</ci><ci>S1</ci><ci>compartment</ci></apply></math>"#,
+        );
+        let expr = parse(math_child(&document)).unwrap();
+        let vars = HashMap::from([
+            ("k1".to_string(), 2.0),
+            ("S1".to_string(), 3.0),
+            ("compartment".to_string(), 5.0),
+        ]);
+        assert_eq!(expr.eval(&vars), 30.0);
+    }
+
+    #[test]
+    fn variables_collects_every_ci_reference_exactly_once() {
+        let document = doc(r#"<math><apply><times/><ci>k1</ci><ci>S1</ci></apply></math>"#);
+        let expr = parse(math_child(&document)).unwrap();
+        let mut names = HashSet::new();
+        expr.variables(&mut names);
+        assert_eq!(names, HashSet::from(["k1".to_string(), "S1".to_string()]));
+    }
+
+    #[test]
+    fn unary_minus_negates_its_single_operand() {
+        let document = doc(r#"<math><apply><minus/><cn>4</cn></apply></math>"#);
+        let expr = parse(math_child(&document)).unwrap();
+        assert_eq!(expr.eval(&HashMap::new()), -4.0);
+    }
+}
@@ -0,0 +1,7 @@
+//! Build [`crate::equation::ODE`] models from external formats instead of
+//! hand-written RHS closures.
+
+mod mathml;
+mod sbml;
+
+pub use sbml::{from_sbml, ImportError, ImportedModel};
@@ -0,0 +1,440 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+
+use roxmltree::{Document, Node};
+
+use super::mathml::{self, Expr, MathError};
+use crate::equation::ODE;
+use crate::{fa, lag};
+
+/// Everything produced by [`from_sbml`]: the runnable [`ODE`] plus the
+/// name -> index maps needed to build a parameter vector or read a
+/// species' state back out.
+pub struct ImportedModel {
+    pub equation: ODE,
+    pub species_index: HashMap<String, usize>,
+    pub parameter_index: HashMap<String, usize>,
+    /// Parameter values as declared in the SBML file, in `parameter_index`
+    /// order — a reasonable starting vector for `estimate_predictions`.
+    pub default_parameters: Vec<f64>,
+}
+
+#[derive(Debug)]
+pub enum ImportError {
+    Io(std::io::Error),
+    Xml(roxmltree::Error),
+    Math(MathError),
+    Missing(String),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Io(e) => write!(f, "failed to read SBML file: {e}"),
+            ImportError::Xml(e) => write!(f, "failed to parse SBML XML: {e}"),
+            ImportError::Math(e) => write!(f, "{e}"),
+            ImportError::Missing(what) => write!(f, "SBML model is missing {what}"),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+impl From<std::io::Error> for ImportError {
+    fn from(e: std::io::Error) -> Self {
+        ImportError::Io(e)
+    }
+}
+
+impl From<roxmltree::Error> for ImportError {
+    fn from(e: roxmltree::Error) -> Self {
+        ImportError::Xml(e)
+    }
+}
+
+impl From<MathError> for ImportError {
+    fn from(e: MathError) -> Self {
+        ImportError::Math(e)
+    }
+}
+
+struct Reaction {
+    reactants: Vec<(usize, f64)>,
+    products: Vec<(usize, f64)>,
+    rate: Expr,
+    /// Reaction-local parameters (SBML `listOfLocalParameters`), which
+    /// shadow same-named global parameters only while evaluating this
+    /// reaction's rate law.
+    locals: HashMap<String, f64>,
+}
+
+/// Parse an SBML model at `path` into an [`ODE`]: species become states,
+/// reactions' kinetic laws become additive flux terms on `dx`, and global
+/// `<parameter>` elements become the parameter vector (in declaration
+/// order, given back via `parameter_index`).
+///
+/// Compartment sizes scale `initialConcentration` into an initial amount,
+/// and are also available by id inside kinetic law expressions (the
+/// standard SBML idiom for an extensive rate, e.g. `k1 * S1 * compartment`);
+/// assignment rules, events and non-constant compartments are not
+/// evaluated — this covers the common case of a fixed-volume reaction
+/// network such as a multi-pool metabolic model. Any `<ci>` identifier in a
+/// kinetic law that isn't a known species, parameter, compartment or local
+/// parameter is rejected at import time rather than left to fail during
+/// simulation.
+pub fn from_sbml(path: impl AsRef<Path>) -> Result<ImportedModel, ImportError> {
+    let text = std::fs::read_to_string(path)?;
+    let doc = Document::parse(&text)?;
+    let model = doc
+        .descendants()
+        .find(|n| n.has_tag_name("model"))
+        .ok_or_else(|| ImportError::Missing("a <model> element".to_string()))?;
+
+    let compartments = parse_compartments(&model);
+    let (parameter_index, default_parameters) = parse_parameters(&model);
+    let (species_index, initial_amounts) = parse_species(&model, &compartments)?;
+    let reactions = parse_reactions(&model, &species_index, &parameter_index, &compartments)?;
+
+    let diffeq_species_index = species_index.clone();
+    let diffeq_parameter_index = parameter_index.clone();
+    let diffeq_compartments = compartments.clone();
+    let nstates = initial_amounts.len();
+    let equation = ODE::new(
+        move |x, p, _t, dx, _rateiv, _cov| {
+            for d in dx.iter_mut() {
+                *d = 0.0;
+            }
+            let mut vars: HashMap<String, f64> =
+                HashMap::with_capacity(x.len() + p.len() + diffeq_compartments.len());
+            for (name, &i) in &diffeq_species_index {
+                vars.insert(name.clone(), x[i]);
+            }
+            for (name, &i) in &diffeq_parameter_index {
+                vars.insert(name.clone(), p[i]);
+            }
+            for (name, &size) in &diffeq_compartments {
+                vars.insert(name.clone(), size);
+            }
+            for reaction in &reactions {
+                let rate = if reaction.locals.is_empty() {
+                    reaction.rate.eval(&vars)
+                } else {
+                    let mut local_vars = vars.clone();
+                    local_vars.extend(reaction.locals.iter().map(|(k, v)| (k.clone(), *v)));
+                    reaction.rate.eval(&local_vars)
+                };
+                for &(i, stoich) in &reaction.reactants {
+                    dx[i] -= stoich * rate;
+                }
+                for &(i, stoich) in &reaction.products {
+                    dx[i] += stoich * rate;
+                }
+            }
+        },
+        |_p| lag! {},
+        |_p| fa! {},
+        move |_p, _t, _cov, x| {
+            x.copy_from_slice(&initial_amounts);
+        },
+        move |x, _p, _t, _cov, y| {
+            y.copy_from_slice(x);
+        },
+        (nstates, nstates),
+    );
+
+    Ok(ImportedModel {
+        equation,
+        species_index,
+        parameter_index,
+        default_parameters,
+    })
+}
+
+fn parse_compartments(model: &Node) -> HashMap<String, f64> {
+    let mut compartments = HashMap::new();
+    if let Some(list) = model.children().find(|n| n.has_tag_name("listOfCompartments")) {
+        for c in list.children().filter(|n| n.has_tag_name("compartment")) {
+            if let Some(id) = c.attribute("id") {
+                let size = c
+                    .attribute("size")
+                    .and_then(|s| s.parse::<f64>().ok())
+                    .unwrap_or(1.0);
+                compartments.insert(id.to_string(), size);
+            }
+        }
+    }
+    compartments
+}
+
+fn parse_parameters(model: &Node) -> (HashMap<String, usize>, Vec<f64>) {
+    let mut index = HashMap::new();
+    let mut values = Vec::new();
+    if let Some(list) = model.children().find(|n| n.has_tag_name("listOfParameters")) {
+        for (i, p) in list
+            .children()
+            .filter(|n| n.has_tag_name("parameter"))
+            .enumerate()
+        {
+            if let Some(id) = p.attribute("id") {
+                let value = p
+                    .attribute("value")
+                    .and_then(|v| v.parse::<f64>().ok())
+                    .unwrap_or(0.0);
+                index.insert(id.to_string(), i);
+                values.push(value);
+            }
+        }
+    }
+    (index, values)
+}
+
+fn parse_species(
+    model: &Node,
+    compartments: &HashMap<String, f64>,
+) -> Result<(HashMap<String, usize>, Vec<f64>), ImportError> {
+    let mut index = HashMap::new();
+    let mut amounts = Vec::new();
+    let list = model
+        .children()
+        .find(|n| n.has_tag_name("listOfSpecies"))
+        .ok_or_else(|| ImportError::Missing("a <listOfSpecies> element".to_string()))?;
+
+    for (i, s) in list.children().filter(|n| n.has_tag_name("species")).enumerate() {
+        let id = s
+            .attribute("id")
+            .ok_or_else(|| ImportError::Missing("a species id".to_string()))?;
+        let amount = if let Some(a) = s.attribute("initialAmount").and_then(|v| v.parse::<f64>().ok()) {
+            a
+        } else if let Some(c) = s
+            .attribute("initialConcentration")
+            .and_then(|v| v.parse::<f64>().ok())
+        {
+            let size = s
+                .attribute("compartment")
+                .and_then(|comp| compartments.get(comp))
+                .copied()
+                .unwrap_or(1.0);
+            c * size
+        } else {
+            0.0
+        };
+        index.insert(id.to_string(), i);
+        amounts.push(amount);
+    }
+    Ok((index, amounts))
+}
+
+fn parse_reactions(
+    model: &Node,
+    species_index: &HashMap<String, usize>,
+    parameter_index: &HashMap<String, usize>,
+    compartments: &HashMap<String, f64>,
+) -> Result<Vec<Reaction>, ImportError> {
+    let mut reactions = Vec::new();
+    let Some(list) = model.children().find(|n| n.has_tag_name("listOfReactions")) else {
+        return Ok(reactions);
+    };
+
+    for r in list.children().filter(|n| n.has_tag_name("reaction")) {
+        let reactants = species_refs(&r, "listOfReactants", species_index)?;
+        let products = species_refs(&r, "listOfProducts", species_index)?;
+
+        let kinetic_law = r
+            .children()
+            .find(|n| n.has_tag_name("kineticLaw"))
+            .ok_or_else(|| ImportError::Missing(format!("kineticLaw for reaction {:?}", r.attribute("id"))))?;
+
+        let locals = parse_local_parameters(&kinetic_law);
+
+        let math = kinetic_law
+            .children()
+            .find(|n| n.has_tag_name("math"))
+            .ok_or_else(|| ImportError::Missing(format!("math for reaction {:?}", r.attribute("id"))))?;
+        let expr_node = math
+            .children()
+            .find(|n| n.is_element())
+            .ok_or_else(|| ImportError::Missing("a kineticLaw expression".to_string()))?;
+        let rate = mathml::parse(expr_node)?;
+
+        // Every `<ci>` the rate law references must resolve to a species, a
+        // global or local parameter, or a compartment — anything else would
+        // make `Expr::eval` panic at simulation time (e.g. a mistyped
+        // parameter name, or an assignment-rule variable we don't support),
+        // so catch it here while we still know which reaction it came from.
+        let mut referenced = HashSet::new();
+        rate.variables(&mut referenced);
+        for name in &referenced {
+            let known = species_index.contains_key(name)
+                || parameter_index.contains_key(name)
+                || compartments.contains_key(name)
+                || locals.contains_key(name);
+            if !known {
+                return Err(ImportError::Missing(format!(
+                    "identifier {name:?} referenced in the rate law of reaction {:?} (not a known species, parameter or compartment)",
+                    r.attribute("id")
+                )));
+            }
+        }
+
+        reactions.push(Reaction {
+            reactants,
+            products,
+            rate,
+            locals,
+        });
+    }
+
+    Ok(reactions)
+}
+
+fn species_refs(
+    reaction: &Node,
+    list_name: &str,
+    species_index: &HashMap<String, usize>,
+) -> Result<Vec<(usize, f64)>, ImportError> {
+    let mut refs = Vec::new();
+    if let Some(list) = reaction.children().find(|n| n.has_tag_name(list_name)) {
+        for sr in list.children().filter(|n| n.has_tag_name("speciesReference")) {
+            let species = sr
+                .attribute("species")
+                .ok_or_else(|| ImportError::Missing("speciesReference species attribute".to_string()))?;
+            let index = *species_index
+                .get(species)
+                .ok_or_else(|| ImportError::Missing(format!("species {species:?} referenced before declaration")))?;
+            let stoichiometry = sr
+                .attribute("stoichiometry")
+                .and_then(|v| v.parse::<f64>().ok())
+                .unwrap_or(1.0);
+            refs.push((index, stoichiometry));
+        }
+    }
+    Ok(refs)
+}
+
+fn parse_local_parameters(kinetic_law: &Node) -> HashMap<String, f64> {
+    let mut locals = HashMap::new();
+    let list_names = ["listOfLocalParameters", "listOfParameters"];
+    for list_name in list_names {
+        if let Some(list) = kinetic_law.children().find(|n| n.has_tag_name(list_name)) {
+            for p in list
+                .children()
+                .filter(|n| n.has_tag_name("localParameter") || n.has_tag_name("parameter"))
+            {
+                if let Some(id) = p.attribute("id") {
+                    let value = p
+                        .attribute("value")
+                        .and_then(|v| v.parse::<f64>().ok())
+                        .unwrap_or(0.0);
+                    locals.insert(id.to_string(), value);
+                }
+            }
+        }
+    }
+    locals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::equation::Equation;
+    use crate::Subject;
+
+    /// Two species in a compartment of size 2, with a mass-action reaction
+    /// `S1 -> S2` whose rate law is `k1 * S1 * compartment` — the standard
+    /// SBML idiom for an extensive rate. If the compartment size were
+    /// ignored (taken as 1) the rate would be `k1 * S1` instead,
+    /// which happens to only be off by a constant factor here, so the
+    /// regression test below checks the actual scaled value rather than
+    /// just "S2 rises".
+    fn mass_action_sbml() -> String {
+        r#"<?xml version="1.0"?>
+<sbml xmlns="http://www.sbml.org/sbml/level3/version1/core" level="3" version="1">
+  <model>
+    <listOfCompartments>
+      <compartment id="compartment" size="2"/>
+    </listOfCompartments>
+    <listOfSpecies>
+      <species id="S1" compartment="compartment" initialAmount="10"/>
+      <species id="S2" compartment="compartment" initialAmount="0"/>
+    </listOfSpecies>
+    <listOfParameters>
+      <parameter id="k1" value="0.1"/>
+    </listOfParameters>
+    <listOfReactions>
+      <reaction id="r1">
+        <listOfReactants>
+          <speciesReference species="S1"/>
+        </listOfReactants>
+        <listOfProducts>
+          <speciesReference species="S2"/>
+        </listOfProducts>
+        <kineticLaw>
+          <math xmlns="http://www.w3.org/1998/Math/MathML">
+            <apply>
+              <times/>
+              <ci>k1</ci>
+              <ci>S1</ci>
+              <ci>compartment</ci>
+            </apply>
+          </math>
+        </kineticLaw>
+      </reaction>
+    </listOfReactions>
+  </model>
+</sbml>"#
+            .to_string()
+    }
+
+    fn write_temp_sbml(name: &str, xml: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(&path, xml).unwrap();
+        path
+    }
+
+    #[test]
+    fn compartment_size_scales_the_reaction_rate() {
+        let path = write_temp_sbml("pharmsol_test_mass_action.xml", &mass_action_sbml());
+        let imported = from_sbml(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let s2 = imported.species_index["S2"];
+        let subject = Subject::builder("1").observation(1.0, 0.0, s2).build();
+        let output = imported
+            .equation
+            .estimate_predictions(&subject, &imported.default_parameters);
+        let predictions = output.get_predictions();
+        let prediction = predictions.iter().find(|p| p.outeq() == s2).unwrap();
+
+        // dS1/dt = -k1*S1*compartment, dS2/dt = +k1*S1*compartment, with
+        // k1=0.1, compartment=2 => effective rate constant 0.2, so
+        // S1(t) = 10*exp(-0.2t) and S2(t) = 10*(1 - exp(-0.2t)).
+        let expected_s2 = 10.0 * (1.0 - (-0.2_f64).exp());
+        assert!(
+            (prediction.prediction() - expected_s2).abs() < 1e-2,
+            "expected S2 ~= {expected_s2}, got {}",
+            prediction.prediction()
+        );
+        // If the compartment size were ignored (taken as 1), the rate would
+        // be wrong by a constant factor of 2 here (rate constant 0.1 instead
+        // of 0.2); this confirms that mistake would be observably different.
+        let wrong_s2 = 10.0 * (1.0 - (-0.1_f64).exp());
+        assert!((prediction.prediction() - wrong_s2).abs() > 1e-2);
+    }
+
+    #[test]
+    fn unresolved_identifier_in_a_rate_law_is_rejected_at_import_time() {
+        let xml = mass_action_sbml().replace("<ci>k1</ci>", "<ci>not_a_real_identifier</ci>");
+        let path = write_temp_sbml("pharmsol_test_unresolved_identifier.xml", &xml);
+        let result = from_sbml(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        match result {
+            Err(ImportError::Missing(message)) => {
+                assert!(message.contains("not_a_real_identifier"));
+            }
+            Err(other) => panic!("expected ImportError::Missing, got {other:?}"),
+            Ok(_) => panic!("expected an error, got Ok"),
+        }
+    }
+}
@@ -0,0 +1,27 @@
+//! `pharmsol`: simulate pharmacokinetic/pharmacodynamic models from a
+//! dosing/observation schedule and a parameter vector.
+//!
+//! The [`equation`] module holds the model backends ([`equation::ODE`] for
+//! numerical integration, [`equation::Analytical`] for closed-form linear
+//! systems); [`Subject`] describes an individual's dosing history and
+//! sampling schedule; [`Prediction`]/[`Predictions`] are what a model
+//! produces.
+
+pub mod covariates;
+pub mod equation;
+pub mod fit;
+pub mod import;
+mod linalg;
+pub mod macros;
+pub mod nca;
+pub mod population;
+pub mod prediction;
+pub mod subject;
+
+pub use covariates::Covariates;
+pub use equation::Equation;
+pub use population::{DataSet, Population};
+pub use prediction::{Prediction, Predictions};
+pub use subject::Subject;
+
+pub use std::collections::HashMap;
@@ -0,0 +1,165 @@
+//! Minimal dense linear algebra used by the closed-form equation backend.
+//!
+//! Models here have at most a handful of compartments, so a small
+//! row-major dense matrix with a Taylor/scaling-and-squaring exponential
+//! and Gaussian-elimination solve is plenty; there's no need to pull in a
+//! full linear algebra crate for 2x2/3x3 systems.
+
+#[derive(Debug, Clone)]
+pub struct Matrix {
+    n: usize,
+    data: Vec<f64>,
+}
+
+impl Matrix {
+    pub fn zeros(n: usize) -> Self {
+        Self {
+            n,
+            data: vec![0.0; n * n],
+        }
+    }
+
+    pub fn identity(n: usize) -> Self {
+        let mut m = Self::zeros(n);
+        for i in 0..n {
+            m.set(i, i, 1.0);
+        }
+        m
+    }
+
+    pub fn from_rows(rows: &[Vec<f64>]) -> Self {
+        let n = rows.len();
+        let mut m = Self::zeros(n);
+        for (i, row) in rows.iter().enumerate() {
+            for (j, &v) in row.iter().enumerate() {
+                m.set(i, j, v);
+            }
+        }
+        m
+    }
+
+    pub fn get(&self, i: usize, j: usize) -> f64 {
+        self.data[i * self.n + j]
+    }
+
+    pub fn set(&mut self, i: usize, j: usize, v: f64) {
+        self.data[i * self.n + j] = v;
+    }
+
+    pub fn mul_mat(&self, other: &Matrix) -> Matrix {
+        let n = self.n;
+        let mut out = Matrix::zeros(n);
+        for i in 0..n {
+            for j in 0..n {
+                let mut sum = 0.0;
+                for k in 0..n {
+                    sum += self.get(i, k) * other.get(k, j);
+                }
+                out.set(i, j, sum);
+            }
+        }
+        out
+    }
+
+    pub fn mul_vec(&self, v: &[f64]) -> Vec<f64> {
+        let n = self.n;
+        let mut out = vec![0.0; n];
+        for (i, out_i) in out.iter_mut().enumerate() {
+            let mut sum = 0.0;
+            for (j, &vj) in v.iter().enumerate() {
+                sum += self.get(i, j) * vj;
+            }
+            *out_i = sum;
+        }
+        out
+    }
+
+    pub fn add(&self, other: &Matrix) -> Matrix {
+        let mut out = self.clone();
+        for i in 0..self.data.len() {
+            out.data[i] += other.data[i];
+        }
+        out
+    }
+
+    pub fn scale(&self, s: f64) -> Matrix {
+        let mut out = self.clone();
+        for v in out.data.iter_mut() {
+            *v *= s;
+        }
+        out
+    }
+
+    fn one_norm(&self) -> f64 {
+        (0..self.n)
+            .map(|j| (0..self.n).map(|i| self.get(i, j).abs()).sum::<f64>())
+            .fold(0.0, f64::max)
+    }
+
+    /// `exp(self)`, via scaling-and-squaring: scale down until the norm is
+    /// small, Taylor-expand, then square back up.
+    pub fn exp(&self) -> Matrix {
+        let norm = self.one_norm();
+        let squarings = if norm <= 0.0 {
+            0
+        } else {
+            (norm.log2().ceil().max(0.0)) as u32
+        };
+        let scale = 2f64.powi(squarings as i32);
+        let scaled = self.scale(1.0 / scale);
+
+        let mut term = Matrix::identity(self.n);
+        let mut sum = Matrix::identity(self.n);
+        for k in 1..=30 {
+            term = term.mul_mat(&scaled).scale(1.0 / k as f64);
+            sum = sum.add(&term);
+        }
+        for _ in 0..squarings {
+            sum = sum.mul_mat(&sum);
+        }
+        sum
+    }
+
+    /// Solve `self * x = b` by Gaussian elimination with partial pivoting.
+    pub fn solve(&self, b: &[f64]) -> Option<Vec<f64>> {
+        let n = self.n;
+        let mut a = self.data.clone();
+        let mut x = b.to_vec();
+
+        for col in 0..n {
+            let pivot = (col..n)
+                .max_by(|&r1, &r2| a[r1 * n + col].abs().partial_cmp(&a[r2 * n + col].abs()).unwrap())
+                .unwrap();
+            if a[pivot * n + col].abs() < 1e-12 {
+                return None;
+            }
+            if pivot != col {
+                for k in 0..n {
+                    a.swap(col * n + k, pivot * n + k);
+                }
+                x.swap(col, pivot);
+            }
+            let diag = a[col * n + col];
+            for row in (col + 1)..n {
+                let factor = a[row * n + col] / diag;
+                if factor == 0.0 {
+                    continue;
+                }
+                for k in col..n {
+                    a[row * n + k] -= factor * a[col * n + k];
+                }
+                x[row] -= factor * x[col];
+            }
+        }
+
+        let mut sol = vec![0.0; n];
+        for row in (0..n).rev() {
+            let mut sum = x[row];
+            for k in (row + 1)..n {
+                sum -= a[row * n + k] * sol[k];
+            }
+            sol[row] = sum / a[row * n + row];
+        }
+        Some(sol)
+    }
+}
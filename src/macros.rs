@@ -0,0 +1,54 @@
+//! Convenience macros used when writing model closures.
+
+/// Bind a slice of parameters to named variables by position.
+///
+/// ```ignore
+/// fetch_params!(p, sa, sb, vab);
+/// // is equivalent to
+/// let sa = p[0];
+/// let sb = p[1];
+/// let vab = p[2];
+/// ```
+#[macro_export]
+macro_rules! fetch_params {
+    ($p:ident, $($name:ident),+ $(,)?) => {
+        let mut __fetch_params_idx = 0usize;
+        $(
+            let $name = $p[__fetch_params_idx];
+            __fetch_params_idx += 1;
+        )+
+        let _ = __fetch_params_idx;
+    };
+}
+
+/// Build the absorption-lag map returned by a model's lag closure.
+///
+/// `lag! {}` yields an empty map (no compartment is lagged); entries are
+/// given as `compartment => lag_time`.
+#[macro_export]
+macro_rules! lag {
+    () => {
+        std::collections::HashMap::new()
+    };
+    ($($k:expr => $v:expr),+ $(,)?) => {{
+        let mut m = std::collections::HashMap::new();
+        $( m.insert($k, $v); )+
+        m
+    }};
+}
+
+/// Build the bioavailability-fraction map returned by a model's fa closure.
+///
+/// `fa! {}` yields an empty map (full bioavailability everywhere); entries
+/// are given as `compartment => fraction`.
+#[macro_export]
+macro_rules! fa {
+    () => {
+        std::collections::HashMap::new()
+    };
+    ($($k:expr => $v:expr),+ $(,)?) => {{
+        let mut m = std::collections::HashMap::new();
+        $( m.insert($k, $v); )+
+        m
+    }};
+}
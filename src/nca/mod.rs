@@ -0,0 +1,326 @@
+//! Noncompartmental analysis (NCA) over simulated [`Prediction`]s.
+//!
+//! [`analyze`] turns the [`Prediction`]s for a single output equation into
+//! the exposure metrics practitioners report (AUClast, AUCall, AUCinf,
+//! Cmax, Tmax, terminal half-life); [`auc`] computes just the area over an
+//! arbitrary [`Interval`], e.g. AUC\[0,10\] on outeq 0, and [`auc_inf`]
+//! (or `auc` with [`Interval::to_infinity`]) extrapolates the tail to
+//! infinity instead.
+
+use crate::Prediction;
+
+/// The upper bound of an [`Interval`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Bound {
+    Time(f64),
+    Infinity,
+}
+
+/// A `[start, end]` window to integrate concentration-time data over.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Interval {
+    pub start: f64,
+    pub end: Bound,
+}
+
+impl Interval {
+    pub fn new(start: f64, end: f64) -> Self {
+        Self {
+            start,
+            end: Bound::Time(end),
+        }
+    }
+
+    pub fn to_infinity(start: f64) -> Self {
+        Self {
+            start,
+            end: Bound::Infinity,
+        }
+    }
+}
+
+/// How area is accumulated between two consecutive concentration-time points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AucMethod {
+    /// `(t2 - t1) * (c1 + c2) / 2` for every segment.
+    Linear,
+    /// Linear trapezoidal on ascending/flat segments; on a strictly
+    /// descending segment with both concentrations positive,
+    /// `(t2 - t1) * (c1 - c2) / ln(c1 / c2)`. Falls back to linear whenever
+    /// either concentration is zero (the log is undefined).
+    LinearUpLogDown,
+}
+
+fn segment_area(t1: f64, c1: f64, t2: f64, c2: f64, method: AucMethod) -> f64 {
+    let dt = t2 - t1;
+    match method {
+        AucMethod::Linear => dt * (c1 + c2) / 2.0,
+        AucMethod::LinearUpLogDown => {
+            if c2 < c1 && c1 > 0.0 && c2 > 0.0 {
+                dt * (c1 - c2) / (c1 / c2).ln()
+            } else {
+                dt * (c1 + c2) / 2.0
+            }
+        }
+    }
+}
+
+/// Concentration at `t`, linearly interpolated between the two points that
+/// bracket it (used to clip a segment to an [`Interval`] boundary).
+fn interpolate(t1: f64, c1: f64, t2: f64, c2: f64, t: f64) -> f64 {
+    if (t2 - t1).abs() < f64::EPSILON {
+        return c1;
+    }
+    c1 + (c2 - c1) * (t - t1) / (t2 - t1)
+}
+
+/// Points for `outeq`, sorted by time.
+fn series(predictions: &[Prediction], outeq: usize) -> Vec<(f64, f64)> {
+    let mut points: Vec<(f64, f64)> = predictions
+        .iter()
+        .filter(|p| p.outeq() == outeq)
+        .map(|p| (p.time(), p.prediction()))
+        .collect();
+    points.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+    points
+}
+
+/// AUC for `outeq` over `interval`, clipping the boundary segments by
+/// linear interpolation when `interval` doesn't land exactly on a sample.
+///
+/// Returns `0.0` if no segment between consecutive samples overlaps the
+/// interval (e.g. fewer than two points for `outeq`); a single sample
+/// inside the interval still contributes its clipped neighbouring
+/// segments. When
+/// `interval.end` is [`Bound::Infinity`], delegates to [`auc_inf`] with the
+/// default 3 terminal points, falling back to `0.0` if too few positive
+/// terminal concentrations are available to estimate `lambda_z`.
+pub fn auc(predictions: &[Prediction], outeq: usize, interval: Interval, method: AucMethod) -> f64 {
+    let end = match interval.end {
+        Bound::Time(end) => end,
+        Bound::Infinity => return auc_inf(predictions, outeq, interval.start, method, 3).unwrap_or(0.0),
+    };
+    let points = series(predictions, outeq);
+    let mut total = 0.0;
+    for window in points.windows(2) {
+        let (t1, c1) = window[0];
+        let (t2, c2) = window[1];
+        if t2 <= interval.start || t1 >= end {
+            continue;
+        }
+        let (ct1, cc1) = if t1 < interval.start {
+            (interval.start, interpolate(t1, c1, t2, c2, interval.start))
+        } else {
+            (t1, c1)
+        };
+        let (ct2, cc2) = if t2 > end {
+            (end, interpolate(t1, c1, t2, c2, end))
+        } else {
+            (t2, c2)
+        };
+        total += segment_area(ct1, cc1, ct2, cc2, method);
+    }
+    total
+}
+
+/// AUC for `outeq` from `start` to infinity: the measured area up to the
+/// last measurable (positive) concentration, plus the extrapolated tail
+/// `Clast / lambda_z`, where `lambda_z` is the slope of a log-linear
+/// regression over the last `terminal_points` positive concentrations.
+///
+/// Returns `None` when fewer than two positive terminal points are
+/// available to estimate `lambda_z` (mirrors [`NcaResult::auc_inf`]).
+pub fn auc_inf(
+    predictions: &[Prediction],
+    outeq: usize,
+    start: f64,
+    method: AucMethod,
+    terminal_points: usize,
+) -> Option<f64> {
+    let points = series(predictions, outeq);
+    let last_measurable = points.iter().rposition(|(_, c)| *c > 0.0)?;
+    let lambda_z = terminal_slope(&points, terminal_points)?;
+    let clast = points[last_measurable].1;
+
+    let mut total = 0.0;
+    for window in points[..=last_measurable].windows(2) {
+        let (t1, c1) = window[0];
+        let (t2, c2) = window[1];
+        if t2 <= start {
+            continue;
+        }
+        let (ct1, cc1) = if t1 < start {
+            (start, interpolate(t1, c1, t2, c2, start))
+        } else {
+            (t1, c1)
+        };
+        total += segment_area(ct1, cc1, t2, c2, method);
+    }
+    Some(total + clast / lambda_z)
+}
+
+/// `lambda_z`, the negated slope of the least-squares regression of
+/// `ln(concentration)` on time over the last `n` strictly positive points,
+/// or `None` if fewer than two such points (or no spread in time) exist.
+fn terminal_slope(points: &[(f64, f64)], n: usize) -> Option<f64> {
+    let tail: Vec<(f64, f64)> = points
+        .iter()
+        .rev()
+        .filter(|(_, c)| *c > 0.0)
+        .take(n)
+        .cloned()
+        .collect();
+    if tail.len() < 2 {
+        return None;
+    }
+    let n = tail.len() as f64;
+    let (sum_t, sum_lnc, sum_t2, sum_t_lnc) = tail.iter().fold(
+        (0.0, 0.0, 0.0, 0.0),
+        |(st, slc, st2, stlc), (t, c)| {
+            let lnc = c.ln();
+            (st + t, slc + lnc, st2 + t * t, stlc + t * lnc)
+        },
+    );
+    let denom = n * sum_t2 - sum_t * sum_t;
+    if denom.abs() < f64::EPSILON {
+        return None;
+    }
+    let slope = (n * sum_t_lnc - sum_t * sum_lnc) / denom;
+    // lambda_z is the (positive) terminal elimination rate, i.e. -slope.
+    Some(-slope)
+}
+
+/// Noncompartmental summary metrics for a single output equation.
+#[derive(Debug, Clone, Copy)]
+pub struct NcaResult {
+    pub outeq: usize,
+    pub cmax: f64,
+    pub tmax: f64,
+    pub auc_last: f64,
+    pub auc_all: f64,
+    /// `None` when fewer than two positive terminal points are available to
+    /// estimate `lambda_z`.
+    pub auc_inf: Option<f64>,
+    pub lambda_z: Option<f64>,
+    pub half_life: Option<f64>,
+}
+
+/// Compute [`NcaResult`] for `outeq`, using the last 3 positive
+/// concentrations to estimate the terminal slope.
+pub fn analyze(predictions: &[Prediction], outeq: usize, method: AucMethod) -> NcaResult {
+    analyze_with_terminal_points(predictions, outeq, method, 3)
+}
+
+/// Like [`analyze`], but with an explicit number of terminal points to
+/// regress `lambda_z` over.
+pub fn analyze_with_terminal_points(
+    predictions: &[Prediction],
+    outeq: usize,
+    method: AucMethod,
+    terminal_points: usize,
+) -> NcaResult {
+    let points = series(predictions, outeq);
+
+    let (tmax, cmax) = points
+        .iter()
+        .cloned()
+        .fold((0.0, f64::MIN), |best, (t, c)| {
+            if c > best.1 {
+                (t, c)
+            } else {
+                best
+            }
+        });
+
+    // AUClast stops at the last measurable (nonzero) concentration.
+    let last_measurable = points.iter().rposition(|(_, c)| *c > 0.0);
+    let auc_last = match last_measurable {
+        Some(idx) => points[..=idx]
+            .windows(2)
+            .map(|w| segment_area(w[0].0, w[0].1, w[1].0, w[1].1, method))
+            .sum(),
+        None => 0.0,
+    };
+
+    // AUCall additionally triangulates the tail down to zero using every
+    // remaining point after the last measurable concentration.
+    let auc_all = points
+        .windows(2)
+        .map(|w| segment_area(w[0].0, w[0].1, w[1].0, w[1].1, method))
+        .sum();
+
+    let lambda_z = terminal_slope(&points, terminal_points);
+    let clast = last_measurable.map(|idx| points[idx].1).unwrap_or(0.0);
+    let auc_inf = lambda_z.map(|lz| auc_last + clast / lz);
+    let half_life = lambda_z.map(|lz| std::f64::consts::LN_2 / lz);
+
+    NcaResult {
+        outeq,
+        cmax,
+        tmax,
+        auc_last,
+        auc_all,
+        auc_inf,
+        lambda_z,
+        half_life,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points(data: &[(f64, f64)]) -> Vec<Prediction> {
+        data.iter().map(|&(t, c)| Prediction::new(t, 0, c)).collect()
+    }
+
+    #[test]
+    fn linear_trapezoidal_matches_hand_computed_area() {
+        let preds = points(&[(0.0, 0.0), (1.0, 10.0), (2.0, 0.0)]);
+        let area = auc(&preds, 0, Interval::new(0.0, 2.0), AucMethod::Linear);
+        assert!((area - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn log_down_falls_back_to_linear_at_zero_concentration() {
+        let preds = points(&[(0.0, 10.0), (1.0, 0.0)]);
+        let log_down = auc(&preds, 0, Interval::new(0.0, 1.0), AucMethod::LinearUpLogDown);
+        let linear = auc(&preds, 0, Interval::new(0.0, 1.0), AucMethod::Linear);
+        assert!((log_down - linear).abs() < 1e-9);
+    }
+
+    #[test]
+    fn auc_clips_partial_boundary_segments_by_interpolation() {
+        let preds = points(&[(0.0, 0.0), (2.0, 10.0)]);
+        let area = auc(&preds, 0, Interval::new(0.0, 1.0), AucMethod::Linear);
+        // Only the first half of the ramp: triangle with base 1, height 5.
+        assert!((area - 2.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn auc_with_infinite_interval_does_not_panic_and_extrapolates_tail() {
+        let preds = points(&[(0.0, 0.0), (1.0, 10.0), (2.0, 5.0), (3.0, 2.5), (4.0, 1.25)]);
+        let value = auc(&preds, 0, Interval::to_infinity(0.0), AucMethod::Linear);
+        let expected = auc_inf(&preds, 0, 0.0, AucMethod::Linear, 3).unwrap();
+        assert!((value - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn auc_inf_is_none_without_enough_terminal_points() {
+        let preds = points(&[(0.0, 0.0), (1.0, 10.0)]);
+        assert!(auc_inf(&preds, 0, 0.0, AucMethod::Linear, 3).is_none());
+    }
+
+    #[test]
+    fn analyze_reports_cmax_tmax_and_terminal_half_life() {
+        let preds = points(&[(0.0, 0.0), (1.0, 10.0), (2.0, 5.0), (3.0, 2.5), (4.0, 1.25)]);
+        let result = analyze(&preds, 0, AucMethod::Linear);
+        assert_eq!(result.cmax, 10.0);
+        assert_eq!(result.tmax, 1.0);
+        let lambda_z = result.lambda_z.expect("enough terminal points");
+        // Concentrations exactly halve every time unit: lambda_z = ln(2).
+        assert!((lambda_z - std::f64::consts::LN_2).abs() < 1e-6);
+        let half_life = result.half_life.expect("half-life from lambda_z");
+        assert!((half_life - 1.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,53 @@
+/// A single simulated observation produced by an [`crate::equation`] model.
+///
+/// `outeq` identifies which output equation (row of the observation
+/// closure's `y`) the value corresponds to, matching the `outeq` a
+/// [`crate::Subject`] observation was scheduled against.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Prediction {
+    time: f64,
+    outeq: usize,
+    prediction: f64,
+}
+
+impl Prediction {
+    pub fn new(time: f64, outeq: usize, prediction: f64) -> Self {
+        Self {
+            time,
+            outeq,
+            prediction,
+        }
+    }
+
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    pub fn outeq(&self) -> usize {
+        self.outeq
+    }
+
+    pub fn prediction(&self) -> f64 {
+        self.prediction
+    }
+}
+
+/// The predictions produced by a single [`crate::equation::Equation::predict_one`] call.
+#[derive(Debug, Clone, Default)]
+pub struct Predictions {
+    predictions: Vec<Prediction>,
+}
+
+impl Predictions {
+    pub fn new(predictions: Vec<Prediction>) -> Self {
+        Self { predictions }
+    }
+
+    pub fn get_predictions(&self) -> &Vec<Prediction> {
+        &self.predictions
+    }
+
+    pub fn into_predictions(self) -> Vec<Prediction> {
+        self.predictions
+    }
+}
@@ -0,0 +1,149 @@
+//! Dosing and observation schedules for a single individual.
+
+/// A single dosing or sampling event in a [`Subject`]'s schedule.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// An instantaneous dose added to a compartment's amount.
+    Bolus {
+        time: f64,
+        amount: f64,
+        compartment: usize,
+    },
+    /// A zero-order infusion delivered to a compartment over `[start, end]`.
+    Infusion {
+        start: f64,
+        end: f64,
+        compartment: usize,
+        rate: f64,
+    },
+    /// A scheduled sample of output equation `outeq`.
+    ///
+    /// `value` carries the observed value (ignored when the subject is only
+    /// used to drive a simulation rather than fit against data).
+    Observation { time: f64, value: f64, outeq: usize },
+}
+
+impl Event {
+    /// The time at which the event occurs (an infusion's start time).
+    pub fn time(&self) -> f64 {
+        match self {
+            Event::Bolus { time, .. } => *time,
+            Event::Infusion { start, .. } => *start,
+            Event::Observation { time, .. } => *time,
+        }
+    }
+}
+
+/// A subject's dosing history and observation schedule.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Subject {
+    id: String,
+    events: Vec<Event>,
+}
+
+impl Subject {
+    pub fn builder(id: impl Into<String>) -> SubjectBuilder {
+        SubjectBuilder::new(id)
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// All events in the order they occur, sorted by time.
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+}
+
+/// Builder for a [`Subject`]'s dosing and observation schedule.
+///
+/// Events are appended in the order the methods are called; [`Self::repeat`]
+/// clones the most recently added event `n` additional times, each shifted
+/// forward by `interval`, which is the common way to express a fixed dosing
+/// or sampling cadence without writing out every timepoint by hand.
+pub struct SubjectBuilder {
+    id: String,
+    events: Vec<Event>,
+}
+
+impl SubjectBuilder {
+    fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            events: Vec::new(),
+        }
+    }
+
+    pub fn bolus(mut self, time: f64, amount: f64, compartment: usize) -> Self {
+        self.events.push(Event::Bolus {
+            time,
+            amount,
+            compartment,
+        });
+        self
+    }
+
+    pub fn infusion(mut self, start: f64, end: f64, compartment: usize, rate: f64) -> Self {
+        self.events.push(Event::Infusion {
+            start,
+            end,
+            compartment,
+            rate,
+        });
+        self
+    }
+
+    pub fn observation(mut self, time: f64, value: f64, outeq: usize) -> Self {
+        self.events.push(Event::Observation { time, value, outeq });
+        self
+    }
+
+    /// Repeat the most recently added event `n` more times, `interval` apart.
+    pub fn repeat(mut self, n: usize, interval: f64) -> Self {
+        let Some(last) = self.events.last().cloned() else {
+            return self;
+        };
+        for i in 1..=n {
+            let offset = interval * i as f64;
+            let shifted = match last.clone() {
+                Event::Bolus {
+                    time,
+                    amount,
+                    compartment,
+                } => Event::Bolus {
+                    time: time + offset,
+                    amount,
+                    compartment,
+                },
+                Event::Infusion {
+                    start,
+                    end,
+                    compartment,
+                    rate,
+                } => Event::Infusion {
+                    start: start + offset,
+                    end: end + offset,
+                    compartment,
+                    rate,
+                },
+                Event::Observation { time, value, outeq } => Event::Observation {
+                    time: time + offset,
+                    value,
+                    outeq,
+                },
+            };
+            self.events.push(shifted);
+        }
+        self
+    }
+
+    pub fn build(mut self) -> Subject {
+        self.events
+            .sort_by(|a, b| a.time().partial_cmp(&b.time()).unwrap());
+        Subject {
+            id: self.id,
+            events: self.events,
+        }
+    }
+}